@@ -0,0 +1,769 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # I'm online Module
+//!
+//! If the local node is a validator (i.e. contains an authority key), this module
+//! gossips a heartbeat transaction with each new session. The heartbeat functions
+//! as a simple mechanism to signal that the node is online in the current era.
+//!
+//! Received heartbeats are tracked for one session and reset with each new session. The
+//! module exposes two public functions to query if a heartbeat has been received in the
+//! current session from a given authority and to report any missing heartbeats.
+//!
+//! The heartbeat is a signed transaction, which was signed using the session key
+//! and includes the recent best block number of the local validators chain as well
+//! as the `NetworkState`.  It is submitted as an Unsigned Extrinsic via off-chain
+//! workers.
+//!
+//! - [`im_online::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//! - [`Module`](./struct.Module.html)
+//!
+//! ## Interface
+//!
+//! ### Public Functions
+//!
+//! - `is_online` - True if the validator sent a heartbeat or authored a block in the current
+//!   session.
+//! - `current_session_liveness` - The full set of current-session validators, each paired with
+//!   whether it is online in the sense of `is_online`.
+//!
+//! ## Usage
+//!
+//! ```
+//! use frame_support::{decl_module, dispatch};
+//! use frame_system::ensure_signed;
+//! use pallet_im_online::{self as im_online};
+//!
+//! pub trait Trait: im_online::Trait {}
+//!
+//! decl_module! {
+//! 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+//! 		#[weight = 0]
+//! 		pub fn is_online(origin, authority_index: u32) -> dispatch::DispatchResult {
+//! 			let _sender = ensure_signed(origin)?;
+//! 			let _is_online = <im_online::Module<T>>::is_online(authority_index);
+//! 			Ok(())
+//! 		}
+//! 	}
+//! }
+//! # fn main() { }
+//! ```
+//!
+//! ## Dependencies
+//!
+//! This module depends on the [Session module](../pallet_session/index.html).
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod mock;
+mod tests;
+
+use sp_application_crypto::RuntimeAppPublic;
+use codec::{Encode, Decode};
+use sp_core::offchain::OpaqueNetworkState;
+use sp_std::prelude::*;
+use sp_runtime::{
+	offchain::storage::StorageValueRef,
+	traits::{Convert, Member, Saturating},
+	Perbill,
+	transaction_validity::{
+		TransactionValidity, ValidTransaction, InvalidTransaction, TransactionSource,
+		TransactionPriority, TransactionLongevity,
+	},
+};
+use sp_staking::{
+	SessionIndex,
+	offence::{ReportOffence, Offence, Kind},
+};
+use frame_support::{
+	decl_module, decl_storage, decl_error, decl_event, Parameter, debug, print,
+	weights::SimpleDispatchInfo,
+	traits::Get,
+};
+use frame_system::ensure_none;
+use frame_support::unsigned::ValidateUnsigned;
+
+pub mod sr25519 {
+	mod app_sr25519 {
+		use sp_application_crypto::{app_crypto, key_types::IM_ONLINE, sr25519};
+		app_crypto!(sr25519, IM_ONLINE);
+	}
+
+	sp_application_crypto::with_pair! {
+		/// An i'm online keypair using sr25519 as its crypto.
+		pub type AuthorityPair = app_sr25519::Pair;
+	}
+
+	/// An i'm online signature using sr25519 as its crypto.
+	pub type AuthoritySignature = app_sr25519::Signature;
+
+	/// An i'm online identifier using sr25519 as its crypto.
+	pub type AuthorityId = app_sr25519::Public;
+}
+
+pub mod ed25519 {
+	mod app_ed25519 {
+		use sp_application_crypto::{app_crypto, key_types::IM_ONLINE, ed25519};
+		app_crypto!(ed25519, IM_ONLINE);
+	}
+
+	sp_application_crypto::with_pair! {
+		/// An i'm online keypair using ed25519 as its crypto.
+		pub type AuthorityPair = app_ed25519::Pair;
+	}
+
+	/// An i'm online signature using ed25519 as its crypto.
+	pub type AuthoritySignature = app_ed25519::Signature;
+
+	/// An i'm online identifier using ed25519 as its crypto.
+	pub type AuthorityId = app_ed25519::Public;
+}
+
+pub trait Trait: pallet_session::historical::Trait + pallet_authorship::Trait {
+	/// The identifier type for an authority.
+	type AuthorityId: Member + Parameter + RuntimeAppPublic + Default + Ord;
+
+	/// A type that gives us the ability to submit unresponsiveness offence reports.
+	type ReportUnresponsiveness:
+		ReportOffence<
+			Self::AccountId,
+			IdentificationTuple<Self>,
+			UnresponsivenessOffence<IdentificationTuple<Self>, Self::UnresponsivenessSlash>,
+		>;
+
+	/// An expected duration of the session.
+	///
+	/// At the start of each session it is used to set `HeartbeatAfter` to roughly the middle
+	/// of the session. The offchain worker does not wait for that block, and the longevity of
+	/// the `heartbeat` transaction does not depend on this parameter.
+	type SessionDuration: Get<Self::BlockNumber>;
+
+	/// A configuration for base priority of unsigned transactions.
+	///
+	/// This is exposed so that it can be tuned for particular runtime, when
+	/// multiple pallets send unsigned transactions.
+	type UnsignedPriority: Get<TransactionPriority>;
+
+	/// A policy used to derive the slashing curve for unresponsiveness offences.
+	///
+	/// Runtimes can plug in a custom curve (e.g. a gentler one for testnets) by providing
+	/// a different `Convert` implementation; `DefaultUnresponsivenessSlash` reproduces the
+	/// curve this pallet has always used.
+	type UnresponsivenessSlash: Convert<(u32, u32), Perbill>;
+
+	/// The number of blocks the offchain worker waits, per `(session_index, authority_index)`
+	/// pair, before it is willing to (re)attempt sending a heartbeat.
+	///
+	/// A heartbeat only counts once it has been included on-chain, but the offchain worker
+	/// runs on every block; without this cooldown it would keep building and
+	/// submitting redundant heartbeat extrinsics for the same session while the first one is
+	/// still waiting to be included.
+	type HeartbeatCooldown: Get<Self::BlockNumber>;
+
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as Trait>::AuthorityId,
+		IdentificationTuple = IdentificationTuple<T>,
+	{
+		/// A new heartbeat was received from `AuthorityId`.
+		HeartbeatReceived(AuthorityId),
+		/// At the end of the session, no offence was committed.
+		AllGood,
+		/// At the end of the session, at least one validator was found to be offline.
+		SomeOffline(Vec<IdentificationTuple>),
+	}
+);
+
+const DB_PREFIX: &[u8] = b"parity/im-online-heartbeat/";
+
+/// The reproduction of the curve this pallet has historically used to slash unresponsive
+/// validators, used as the default for [`Trait::UnresponsivenessSlash`].
+pub struct DefaultUnresponsivenessSlash;
+
+impl Convert<(u32, u32), Perbill> for DefaultUnresponsivenessSlash {
+	fn convert((offenders_count, validator_set_count): (u32, u32)) -> Perbill {
+		// Validators that are within the tolerated 10% (plus one, to avoid slashing the very
+		// first offender past the threshold) are not slashed at all; beyond that the fraction
+		// climbs linearly.
+		let threshold = validator_set_count / 10 + 1;
+		let excess = offenders_count.saturating_sub(threshold);
+		Perbill::from_rational_approximation(21 * excess, validator_set_count * 100)
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as ImOnline {
+		/// A block number roughly in the middle of the current session.
+		///
+		/// At the beginning of each session we set this from `SessionDuration`.
+		/// The offchain worker does not read it: heartbeats are not held back until
+		/// this block.
+		HeartbeatAfter get(fn heartbeat_after): T::BlockNumber;
+
+		/// The current set of keys that may issue a heartbeat.
+		Keys get(fn keys): Vec<T::AuthorityId>;
+
+		/// For each session index, we keep a mapping of `AuthIndex` to
+		/// `offchain::OpaqueNetworkState`.
+		ReceivedHeartbeats get(fn received_heartbeats):
+			double_map hasher(twox_64_concat) SessionIndex, hasher(twox_64_concat) u32
+			=> Option<Vec<u8>>;
+
+		/// For each session index, we keep a mapping of `ValidatorId<T>` to the
+		/// number of blocks authored by the given authority.
+		AuthoredBlocks get(fn authored_blocks):
+			double_map hasher(twox_64_concat) SessionIndex, hasher(twox_64_concat) T::ValidatorId
+			=> u32;
+	}
+	add_extra_genesis {
+		config(keys): Vec<T::AuthorityId>;
+		build(|config| Module::<T>::initialize_keys(&config.keys))
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// Non existent public key.
+		InvalidKey,
+		/// Duplicated heartbeat.
+		DuplicatedHeartbeat,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// # <weight>
+		/// - Complexity: `O(K + E)` where K is length of `Keys` (heartbeat.validators_len)
+		///   and E is length of `heartbeat.network_state.external_address`
+		///   - `O(K)`: decoding of length `K`
+		///   - `O(E)`: decoding/encoding of length `E`
+		/// - DbReads: pallet_session `Validators`, `CurrentIndex`, `Keys`
+		/// - DbWrites: `ReceivedHeartbeats`
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		fn heartbeat(
+			origin,
+			heartbeat: Heartbeat<T::BlockNumber>,
+			// since signature verification is done in `validate_unsigned`
+			// we can skip doing it here again.
+			_signature: <T::AuthorityId as RuntimeAppPublic>::Signature,
+		) {
+			ensure_none(origin)?;
+
+			let current_session = <pallet_session::Module<T>>::current_index();
+			if heartbeat.session_index != current_session {
+				Err(Error::<T>::InvalidKey)?
+			}
+
+			let exists = ReceivedHeartbeats::contains_key(
+				&current_session,
+				&heartbeat.authority_index
+			);
+			let keys = Keys::<T>::get();
+			let public = keys.get(heartbeat.authority_index as usize);
+			if let (false, Some(public)) = (exists, public) {
+				let network_state = heartbeat.network_state.encode();
+				ReceivedHeartbeats::insert(
+					&current_session,
+					&heartbeat.authority_index,
+					&network_state
+				);
+
+				Self::deposit_event(RawEvent::HeartbeatReceived(public.clone()));
+			} else if exists {
+				Err(Error::<T>::DuplicatedHeartbeat)?
+			} else {
+				Err(Error::<T>::InvalidKey)?
+			}
+		}
+
+		// Runs after every block.
+		fn offchain_worker(block_number: T::BlockNumber) {
+			// Only send messages if we are a potential validator.
+			if sp_io::offchain::is_validator() {
+				for res in Self::send_heartbeats(block_number).into_iter().flatten() {
+					if let Err(e) = res {
+						debug::debug!(
+							target: "im_online",
+							"Skipping heartbeat at {:?}: {:?}",
+							block_number,
+							e,
+						)
+					}
+				}
+			} else {
+				debug::trace!(
+					target: "im_online",
+					"Skipping heartbeat at {:?}. Not a validator.",
+					block_number,
+				)
+			}
+		}
+	}
+}
+
+/// Keep track of number of authored blocks per authority, uncles are counted as
+/// well since they're a valid proof of being online.
+impl<T: Trait + pallet_authorship::Trait> pallet_authorship::EventHandler<T::ValidatorId, T::BlockNumber> for Module<T> {
+	fn note_author(author: T::ValidatorId) {
+		Self::note_authorship(author);
+	}
+
+	fn note_uncle(author: T::ValidatorId, _age: T::BlockNumber) {
+		Self::note_authorship(author);
+	}
+}
+
+type OffchainResult<T> = Result<T, OffchainErr>;
+
+/// Error which may occur while executing the off-chain code.
+#[cfg_attr(test, derive(PartialEq))]
+pub enum OffchainErr {
+	TooEarly,
+	WaitingForInclusion,
+	AlreadySent,
+	AlreadyOnline(u32),
+	FailedSigning,
+	NetworkState,
+	SubmitTransaction,
+}
+
+impl sp_std::fmt::Debug for OffchainErr {
+	fn fmt(&self, fmt: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		match *self {
+			OffchainErr::TooEarly => write!(fmt, "Too early to send heartbeat."),
+			OffchainErr::WaitingForInclusion => write!(fmt, "Heartbeat already sent and is waiting for inclusion."),
+			OffchainErr::AlreadySent => write!(fmt, "Heartbeat already sent."),
+			OffchainErr::AlreadyOnline(auth_idx) => write!(fmt, "Authority {} is already online", auth_idx),
+			OffchainErr::FailedSigning => write!(fmt, "Failed to sign heartbeat."),
+			OffchainErr::NetworkState => write!(fmt, "Failed to fetch network state."),
+			OffchainErr::SubmitTransaction => write!(fmt, "Failed to submit transaction."),
+		}
+	}
+}
+
+/// A type for representing the validator id in a session.
+pub type ValidatorId<T> = <T as pallet_session::Trait>::ValidatorId;
+
+/// A tuple of (ValidatorId, Identification) where `Identification` is the full identification
+/// of `ValidatorId`.
+pub type IdentificationTuple<T> = pallet_session::historical::IdentificationTuple<T>;
+
+/// Heartbeat of a single authority that is supposed to be running at the current block.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, sp_runtime::RuntimeDebug)]
+pub struct Heartbeat<BlockNumber>
+	where BlockNumber: Encode + Decode,
+{
+	/// Block number at the time heartbeat is created.
+	pub block_number: BlockNumber,
+	/// A state of the network.
+	pub network_state: OpaqueNetworkState,
+	/// Index of the current session.
+	pub session_index: SessionIndex,
+	/// An index of the authority on the list of validators.
+	pub authority_index: AuthIndex,
+}
+
+/// Index of an authority.
+pub type AuthIndex = u32;
+
+impl<T: Trait> Module<T> {
+	/// Returns `true` if, in the current session, a heartbeat has been received for the
+	/// authority at `authority_index` or that authority has authored at least one block,
+	/// otherwise `false`.
+	pub fn is_online(authority_index: AuthIndex) -> bool {
+		let current_validators = <pallet_session::Module<T>>::validators();
+
+		if authority_index >= current_validators.len() as u32 {
+			return false;
+		}
+
+		let authority = &current_validators[authority_index as usize];
+
+		Self::is_online_aux(authority_index, authority)
+	}
+
+	/// Returns the full set of validators for the current session together with whether each
+	/// of them is online (see [`Self::is_online`]: a heartbeat was received or a block was
+	/// authored), in validator (authority) index order.
+	pub fn current_session_liveness() -> Vec<(ValidatorId<T>, bool)> {
+		<pallet_session::Module<T>>::validators()
+			.into_iter()
+			.enumerate()
+			.map(|(index, validator)| {
+				let online = Self::is_online_aux(index as u32, &validator);
+				(validator, online)
+			})
+			.collect()
+	}
+
+	fn is_online_aux(authority_index: AuthIndex, authority: &ValidatorId<T>) -> bool {
+		let current_session = <pallet_session::Module<T>>::current_index();
+
+		if ReceivedHeartbeats::contains_key(&current_session, &authority_index) {
+			true
+		} else {
+			let last_seen_block = <AuthoredBlocks<T>>::get(current_session, authority);
+
+			last_seen_block > 0
+		}
+	}
+
+	fn note_authorship(author: T::ValidatorId) {
+		let current_session = <pallet_session::Module<T>>::current_index();
+		<AuthoredBlocks<T>>::mutate(&current_session, author, |authored| {
+			*authored += 1;
+		});
+	}
+
+	pub(crate) fn initialize_keys(keys: &[T::AuthorityId]) {
+		if !keys.is_empty() {
+			assert!(Keys::<T>::get().is_empty(), "Keys are already initialized!");
+			Keys::<T>::put(keys);
+		}
+	}
+
+	/// The local storage key under which we keep the last block at which we submitted a
+	/// heartbeat for the given `(session_index, authority_index)` pair.
+	fn lock_db_key(session_index: SessionIndex, authority_index: AuthIndex) -> Vec<u8> {
+		(DB_PREFIX, session_index, authority_index).encode()
+	}
+
+	/// Returns `true` iff a heartbeat for `(session_index, authority_index)` hasn't already
+	/// been submitted within the last `T::HeartbeatCooldown::get()` blocks.
+	///
+	/// This is a guard against the offchain worker re-building and submitting a heartbeat for
+	/// an authority on every block of a session while the first one is still waiting to be
+	/// included on-chain; `ReceivedHeartbeats` alone can't catch that since it's only populated
+	/// once a heartbeat lands. This is only a cheap up-front check: it doesn't claim the slot,
+	/// so a successful send must still be followed by [`Self::claim_heartbeat_slot`].
+	fn should_send_heartbeat(
+		block_number: T::BlockNumber,
+		session_index: SessionIndex,
+		authority_index: AuthIndex,
+	) -> bool {
+		let key = Self::lock_db_key(session_index, authority_index);
+		let storage = StorageValueRef::persistent(&key);
+
+		match storage.get::<T::BlockNumber>().flatten() {
+			Some(last) => block_number >= last.saturating_add(T::HeartbeatCooldown::get()),
+			None => true,
+		}
+	}
+
+	/// Atomically records `block_number` as the last block at which a heartbeat for
+	/// `(session_index, authority_index)` was sent, provided the cooldown has indeed elapsed.
+	///
+	/// Unlike `should_send_heartbeat`, this performs the write via `StorageValueRef::mutate`,
+	/// so it must only be called once a heartbeat has actually been submitted: claiming the
+	/// slot up front, before signing and submitting, would leave the authority sitting out a
+	/// full cooldown window if signing or submission failed without ever having sent anything.
+	fn claim_heartbeat_slot(
+		block_number: T::BlockNumber,
+		session_index: SessionIndex,
+		authority_index: AuthIndex,
+	) -> bool {
+		let key = Self::lock_db_key(session_index, authority_index);
+		let storage = StorageValueRef::persistent(&key);
+
+		let res = storage.mutate(|last_attempt: Option<Option<T::BlockNumber>>| {
+			match last_attempt.flatten() {
+				Some(last) if block_number < last.saturating_add(T::HeartbeatCooldown::get()) => {
+					Err(())
+				},
+				_ => Ok(block_number),
+			}
+		});
+
+		res.is_ok()
+	}
+
+	fn send_heartbeats(block_number: T::BlockNumber) -> OffchainResult<impl Iterator<Item = OffchainResult<()>>> {
+		let keys = Keys::<T>::get();
+		let current_validators = <pallet_session::Module<T>>::validators();
+		let session_index = <pallet_session::Module<T>>::current_index();
+
+		let mut local_keys = T::AuthorityId::all();
+		local_keys.sort();
+		let local_keys = keys.into_iter().enumerate().filter_map(|(index, authority_id)| {
+			local_keys.binary_search(&authority_id)
+				.ok()
+				.map(|_| (index as u32, authority_id))
+		}).collect::<Vec<_>>();
+
+		let network_state = sp_io::offchain::network_state()
+			.map_err(|_| OffchainErr::NetworkState)?;
+
+		Ok(local_keys.into_iter().map(move |(authority_index, key)| {
+			if Self::is_online(authority_index) {
+				return Err(OffchainErr::AlreadyOnline(authority_index));
+			}
+
+			if !Self::should_send_heartbeat(block_number, session_index, authority_index) {
+				return Err(OffchainErr::TooEarly);
+			}
+
+			let heartbeat_data = Heartbeat {
+				block_number,
+				network_state: network_state.clone(),
+				session_index,
+				authority_index,
+			};
+
+			let signature = key.sign(&heartbeat_data.encode()).ok_or(OffchainErr::FailedSigning)?;
+			let call = Call::heartbeat(heartbeat_data, signature);
+
+			frame_support::debug::info!(
+				target: "im_online",
+				"[index: {}] Reporting im-online at block: {:?} (Validators: {:?})",
+				authority_index,
+				block_number,
+				current_validators,
+			);
+
+			let _ = <frame_system::offchain::SubmitTransaction<T, Call<T>>>::submit_unsigned(call)
+				.map_err(|_| OffchainErr::SubmitTransaction)?;
+
+			// Only claim the cooldown slot now that the heartbeat has actually been submitted;
+			// if another worker raced us to it that's fine, our heartbeat is in the pool either
+			// way.
+			let _ = Self::claim_heartbeat_slot(block_number, session_index, authority_index);
+
+			Ok(())
+		}))
+	}
+}
+
+impl<T: Trait> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
+	type Public = T::AuthorityId;
+}
+
+impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
+	type Key = T::AuthorityId;
+
+	fn on_genesis_session<'a, I: 'a>(validators: I)
+		where I: Iterator<Item = (&'a T::AccountId, T::AuthorityId)>
+	{
+		let keys = validators.map(|x| x.1).collect::<Vec<_>>();
+		Self::initialize_keys(&keys);
+	}
+
+	fn on_new_session<'a, I: 'a>(_changed: bool, validators: I, _queued_validators: I)
+		where I: Iterator<Item = (&'a T::AccountId, T::AuthorityId)>
+	{
+		// Record roughly the middle of the new session in `HeartbeatAfter`.
+		let now = <frame_system::Module<T>>::block_number();
+		let session_duration = T::SessionDuration::get();
+		<HeartbeatAfter<T>>::put(now.saturating_add(session_duration / 2.into()));
+
+		// Remember who the authorities are for the new session.
+		let keys = validators.map(|x| x.1).collect::<Vec<_>>();
+		Keys::<T>::put(keys);
+	}
+
+	fn on_before_session_ending() {
+		let session_index = <pallet_session::Module<T>>::current_index();
+		let current_validators = <pallet_session::Module<T>>::validators();
+		let validator_set_count = current_validators.len() as u32;
+
+		let offenders = current_validators.into_iter().enumerate()
+			.filter(|(index, id)| !Self::is_online_aux(*index as u32, id))
+			.filter_map(|(_index, id)| {
+				T::FullIdentificationOf::convert(id.clone()).map(|full_id| (id, full_id))
+			})
+			.collect::<Vec<IdentificationTuple<T>>>();
+
+		// Remove all received heartbeats and number of authored blocks from the
+		// current session, they have already been processed and won't be needed
+		// anymore.
+		ReceivedHeartbeats::remove_prefix(&session_index);
+		<AuthoredBlocks<T>>::remove_prefix(&session_index);
+
+		if offenders.is_empty() {
+			Self::deposit_event(RawEvent::AllGood);
+		} else {
+			Self::deposit_event(RawEvent::SomeOffline(offenders.clone()));
+
+			let offence = UnresponsivenessOffence::<_, T::UnresponsivenessSlash> {
+				session_index,
+				validator_set_count,
+				offenders,
+				_slash: Default::default(),
+			};
+			if let Err(e) = T::ReportUnresponsiveness::report_offence(vec![], offence) {
+				print(e);
+			}
+		}
+	}
+
+	fn on_disabled(_i: usize) {
+		// ignore
+	}
+}
+
+impl<T: Trait> ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		if let Call::heartbeat(heartbeat, signature) = call {
+			if <Module<T>>::is_online(heartbeat.authority_index) {
+				// we already received a heartbeat for this authority
+				return InvalidTransaction::Stale.into();
+			}
+
+			// check if session index from heartbeat is recent
+			let current_session = <pallet_session::Module<T>>::current_index();
+			if heartbeat.session_index != current_session {
+				return InvalidTransaction::Stale.into();
+			}
+
+			// verify that the incoming (unverified) pubkey is actually an authority id
+			let keys = Keys::<T>::get();
+			let authority_id = match keys.get(heartbeat.authority_index as usize) {
+				Some(id) => id,
+				None => return InvalidTransaction::BadProof.into(),
+			};
+
+			// check signature (this is expensive so we do it last).
+			let signature_valid = heartbeat.using_encoded(|encoded_heartbeat| {
+				authority_id.verify(&encoded_heartbeat, &signature)
+			});
+
+			if !signature_valid {
+				return InvalidTransaction::BadProof.into();
+			}
+
+			ValidTransaction::with_tag_prefix("ImOnline")
+				.priority(T::UnsignedPriority::get())
+				.and_provides((current_session, authority_id))
+				.longevity(TransactionLongevity::max_value())
+				.propagate(true)
+				.build()
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+
+	fn pre_dispatch(call: &Self::Call) -> Result<(), frame_support::unsigned::TransactionValidityError> {
+		if let Call::heartbeat(heartbeat, _signature) = call {
+			let current_session = <pallet_session::Module<T>>::current_index();
+			if heartbeat.session_index != current_session {
+				return Err(InvalidTransaction::Stale.into());
+			}
+
+			if <Module<T>>::is_online(heartbeat.authority_index) {
+				return Err(InvalidTransaction::Stale.into());
+			}
+
+			Ok(())
+		} else {
+			Err(InvalidTransaction::Call.into())
+		}
+	}
+}
+
+/// An offence that is filed if a validator didn't send a heartbeat message.
+///
+/// The `Slash` type parameter selects the curve used to turn the proportion of unresponsive
+/// validators into a slash fraction; it defaults to [`DefaultUnresponsivenessSlash`], the curve
+/// this pallet has always used, but a runtime can plug in its own via
+/// [`Trait::UnresponsivenessSlash`].
+pub struct UnresponsivenessOffence<Offender, Slash = DefaultUnresponsivenessSlash> {
+	/// The current session index in which we report the unresponsive validators.
+	///
+	/// It acts as a time measure for unresponsiveness reports and effectively will always point
+	/// at the end of the session.
+	pub session_index: SessionIndex,
+	/// The size of the validator set in current era.
+	pub validator_set_count: u32,
+	/// Authorities that were unresponsive during the current era.
+	pub offenders: Vec<Offender>,
+	/// The slashing curve to apply; carries no data, only selects an implementation of
+	/// `Convert<(u32, u32), Perbill>`.
+	pub _slash: sp_std::marker::PhantomData<Slash>,
+}
+
+impl<Offender: Clone, Slash> Clone for UnresponsivenessOffence<Offender, Slash> {
+	fn clone(&self) -> Self {
+		UnresponsivenessOffence {
+			session_index: self.session_index,
+			validator_set_count: self.validator_set_count,
+			offenders: self.offenders.clone(),
+			_slash: sp_std::marker::PhantomData,
+		}
+	}
+}
+
+impl<Offender: PartialEq, Slash> PartialEq for UnresponsivenessOffence<Offender, Slash> {
+	fn eq(&self, other: &Self) -> bool {
+		self.session_index == other.session_index
+			&& self.validator_set_count == other.validator_set_count
+			&& self.offenders == other.offenders
+	}
+}
+
+impl<Offender: Eq, Slash> Eq for UnresponsivenessOffence<Offender, Slash> {}
+
+impl<Offender: sp_std::fmt::Debug, Slash> sp_std::fmt::Debug for UnresponsivenessOffence<Offender, Slash> {
+	fn fmt(&self, fmt: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		fmt.debug_struct("UnresponsivenessOffence")
+			.field("session_index", &self.session_index)
+			.field("validator_set_count", &self.validator_set_count)
+			.field("offenders", &self.offenders)
+			.finish()
+	}
+}
+
+impl<Offender: Clone, Slash: Convert<(u32, u32), Perbill>> Offence<Offender>
+	for UnresponsivenessOffence<Offender, Slash>
+{
+	const ID: Kind = *b"im-online:offlin";
+	type TimeSlot = SessionIndex;
+
+	fn offenders(&self) -> Vec<Offender> {
+		self.offenders.clone()
+	}
+
+	fn session_index(&self) -> SessionIndex {
+		self.session_index
+	}
+
+	fn validator_set_count(&self) -> u32 {
+		self.validator_set_count
+	}
+
+	fn time_slot(&self) -> Self::TimeSlot {
+		self.session_index
+	}
+
+	fn slash_fraction(
+		offenders_count: u32,
+		validator_set_count: u32,
+	) -> Perbill {
+		Slash::convert((offenders_count, validator_set_count))
+	}
+}
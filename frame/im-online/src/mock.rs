@@ -0,0 +1,203 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Test utilities
+
+#![cfg(test)]
+
+use std::cell::RefCell;
+use crate as im_online;
+use crate::{Module, Trait, DefaultUnresponsivenessSlash};
+use sp_runtime::Perbill;
+use sp_runtime::testing::{Header, UintAuthorityId, TestXt};
+use sp_runtime::traits::{IdentityLookup, Convert, BlakeTwo256};
+use sp_staking::{SessionIndex, offence::{ReportOffence, OffenceError}};
+use frame_support::{impl_outer_origin, impl_outer_dispatch, impl_outer_event, parameter_types};
+use frame_support::weights::Weight;
+use sp_core::H256;
+
+impl_outer_origin! {
+	pub enum Origin for Runtime {}
+}
+
+impl_outer_dispatch! {
+	pub enum Call for Runtime where origin: Origin {
+		im_online::ImOnline,
+	}
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Runtime {
+		frame_system<T>,
+		pallet_session,
+		im_online<T>,
+	}
+}
+
+// Workaround for https://github.com/rust-lang/rust/issues/26925 . Remove when sorted.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Runtime;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for Runtime {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = Call;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = TestEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = ();
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+}
+
+parameter_types! {
+	pub const UncleGenerations: u64 = 5;
+}
+
+impl pallet_authorship::Trait for Runtime {
+	type FindAuthor = ();
+	type UncleGenerations = UncleGenerations;
+	type FilterUncle = ();
+	type EventHandler = Module<Runtime>;
+}
+
+thread_local! {
+	pub static VALIDATORS: RefCell<Option<Vec<UintAuthorityId>>> = RefCell::new(Some(vec![
+		UintAuthorityId(1),
+		UintAuthorityId(2),
+		UintAuthorityId(3),
+	]));
+}
+
+pub struct TestSessionManager;
+impl pallet_session::SessionManager<UintAuthorityId> for TestSessionManager {
+	fn end_session(_: SessionIndex) {}
+	fn start_session(_: SessionIndex) {}
+	fn new_session(_: SessionIndex) -> Option<Vec<UintAuthorityId>> {
+		VALIDATORS.with(|l| l.borrow_mut().take())
+	}
+}
+
+impl pallet_session::historical::Trait for Runtime {
+	type FullIdentification = UintAuthorityId;
+	type FullIdentificationOf = sp_runtime::traits::ConvertInto;
+}
+
+parameter_types! {
+	pub const Period: u64 = 1;
+	pub const Offset: u64 = 0;
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(16);
+}
+
+impl pallet_session::Trait for Runtime {
+	type SessionManager = TestSessionManager;
+	type SessionHandler = (ImOnline,);
+	type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
+	type Event = TestEvent;
+	type Keys = UintAuthorityId;
+	type ValidatorId = UintAuthorityId;
+	type ValidatorIdOf = sp_runtime::traits::ConvertInto;
+	type SelectInitialValidators = ();
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+}
+
+thread_local! {
+	pub static OFFENCES: RefCell<Vec<(Vec<u64>, crate::UnresponsivenessOffence<(UintAuthorityId, UintAuthorityId)>)>> =
+		RefCell::new(vec![]);
+}
+
+/// A mock offence report handler.
+pub struct OffenceHandler;
+impl ReportOffence<u64, (UintAuthorityId, UintAuthorityId), crate::UnresponsivenessOffence<(UintAuthorityId, UintAuthorityId)>>
+	for OffenceHandler
+{
+	fn report_offence(
+		reporters: Vec<u64>,
+		offence: crate::UnresponsivenessOffence<(UintAuthorityId, UintAuthorityId)>,
+	) -> Result<(), OffenceError> {
+		OFFENCES.with(|l| l.borrow_mut().push((reporters, offence)));
+		Ok(())
+	}
+
+	fn is_known_offence(_offenders: &[(UintAuthorityId, UintAuthorityId)], _time_slot: &SessionIndex) -> bool {
+		false
+	}
+}
+
+parameter_types! {
+	pub const SessionDuration: u64 = 10;
+	pub const UnsignedPriority: u64 = 1 << 20;
+	pub const HeartbeatCooldown: u64 = 2;
+}
+
+/// A gentler slashing curve used to verify that runtimes can swap out the default policy.
+pub struct LenientUnresponsivenessSlash;
+impl Convert<(u32, u32), Perbill> for LenientUnresponsivenessSlash {
+	fn convert(input: (u32, u32)) -> Perbill {
+		DefaultUnresponsivenessSlash::convert(input) / 2
+	}
+}
+
+impl Trait for Runtime {
+	type AuthorityId = UintAuthorityId;
+	type ReportUnresponsiveness = OffenceHandler;
+	type SessionDuration = SessionDuration;
+	type UnsignedPriority = UnsignedPriority;
+	type UnresponsivenessSlash = DefaultUnresponsivenessSlash;
+	type HeartbeatCooldown = HeartbeatCooldown;
+	type Event = TestEvent;
+}
+
+pub type System = frame_system::Module<Runtime>;
+pub type Session = pallet_session::Module<Runtime>;
+pub type ImOnline = Module<Runtime>;
+
+pub type Extrinsic = TestXt<Call, ()>;
+
+impl<LocalCall> frame_system::offchain::SendTransactionTypes<LocalCall> for Runtime where
+	Call: From<LocalCall>,
+{
+	type OverarchingCall = Call;
+	type Extrinsic = Extrinsic;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	t.into()
+}
+
+pub fn advance_session() {
+	let now = System::block_number().max(1);
+	System::set_block_number(now + 1);
+	Session::rotate_session();
+}
@@ -31,29 +31,45 @@ use sp_runtime::testing::UintAuthorityId;
 
 #[test]
 fn test_unresponsiveness_slash_fraction() {
+	// The default policy, `DefaultUnresponsivenessSlash`, reproduces the curve this pallet
+	// has always used.
+	type Offence = UnresponsivenessOffence<(), DefaultUnresponsivenessSlash>;
+
 	// A single case of unresponsiveness is not slashed.
 	assert_eq!(
-		UnresponsivenessOffence::<()>::slash_fraction(1, 50),
+		Offence::slash_fraction(1, 50),
 		Perbill::zero(),
 	);
 
 	assert_eq!(
-		UnresponsivenessOffence::<()>::slash_fraction(5, 50),
+		Offence::slash_fraction(5, 50),
 		Perbill::zero(), // 0%
 	);
 
 	assert_eq!(
-		UnresponsivenessOffence::<()>::slash_fraction(7, 50),
+		Offence::slash_fraction(7, 50),
 		Perbill::from_parts(4200000), // 0.42%
 	);
 
 	// One third offline should be punished around 5%.
 	assert_eq!(
-		UnresponsivenessOffence::<()>::slash_fraction(17, 50),
+		Offence::slash_fraction(17, 50),
 		Perbill::from_parts(46200000), // 4.62%
 	);
 }
 
+#[test]
+fn slash_fraction_policy_is_pluggable() {
+	// A runtime can swap in its own curve; the mock's `LenientUnresponsivenessSlash` halves
+	// the default policy's output.
+	type Offence = UnresponsivenessOffence<(), LenientUnresponsivenessSlash>;
+
+	assert_eq!(
+		Offence::slash_fraction(17, 50),
+		Perbill::from_parts(46200000) / 2,
+	);
+}
+
 #[test]
 fn should_report_offline_validators() {
 	new_test_ext().execute_with(|| {
@@ -89,8 +105,17 @@ fn should_report_offline_validators() {
 					(UintAuthorityId(2), UintAuthorityId(2)),
 					(UintAuthorityId(3), UintAuthorityId(3)),
 				],
+				_slash: Default::default(),
 			})
 		]);
+		assert_eq!(
+			last_im_online_event(),
+			TestEvent::im_online(RawEvent::SomeOffline(vec![
+				(UintAuthorityId(1), UintAuthorityId(1)),
+				(UintAuthorityId(2), UintAuthorityId(2)),
+				(UintAuthorityId(3), UintAuthorityId(3)),
+			])),
+		);
 
 		// should not report when heartbeat is sent
 		for (idx, v) in validators.into_iter().take(4).enumerate() {
@@ -108,11 +133,66 @@ fn should_report_offline_validators() {
 					(UintAuthorityId(5), UintAuthorityId(5)),
 					(UintAuthorityId(6), UintAuthorityId(6)),
 				],
+				_slash: Default::default(),
 			})
 		]);
+		assert_eq!(
+			last_im_online_event(),
+			TestEvent::im_online(RawEvent::SomeOffline(vec![
+				(UintAuthorityId(5), UintAuthorityId(5)),
+				(UintAuthorityId(6), UintAuthorityId(6)),
+			])),
+		);
+	});
+}
+
+#[test]
+fn should_emit_all_good_event_when_no_offenders() {
+	new_test_ext().execute_with(|| {
+		// given
+		let block = 1;
+		System::set_block_number(block);
+		// buffer new validators
+		Session::rotate_session();
+		// enact the change and buffer another one
+		let validators = vec![
+			UintAuthorityId(1),
+			UintAuthorityId(2),
+			UintAuthorityId(3),
+			UintAuthorityId(4),
+			UintAuthorityId(5),
+			UintAuthorityId(6),
+		];
+		VALIDATORS.with(|l| *l.borrow_mut() = Some(validators.clone()));
+		Session::rotate_session();
+
+		// when every validator of the current session sends a heartbeat before it ends
+		for (idx, v) in validators.into_iter().take(3).enumerate() {
+			let _ = heartbeat(block, 2, idx as u32, v).unwrap();
+		}
+		Session::rotate_session();
+
+		// then
+		let offences = OFFENCES.with(|l| l.replace(vec![]));
+		assert!(offences.is_empty());
+		assert_eq!(
+			last_im_online_event(),
+			TestEvent::im_online(RawEvent::AllGood),
+		);
 	});
 }
 
+/// The last event deposited by this pallet, skipping events of other pallets (such as the
+/// session pallet's `NewSession`).
+fn last_im_online_event() -> TestEvent {
+	System::events()
+		.into_iter()
+		.map(|record| record.event)
+		.filter(|event| if let TestEvent::im_online(_) = event { true } else { false })
+		.last()
+		.expect("an im-online event was deposited")
+}
+
 fn heartbeat(
 	block_number: u64,
 	session_index: u32,
@@ -177,6 +257,11 @@ fn should_mark_online_validator_when_heartbeat_is_received() {
 		assert!(ImOnline::is_online(0));
 		assert!(!ImOnline::is_online(1));
 		assert!(!ImOnline::is_online(2));
+		assert_eq!(
+			System::events().last().unwrap().event,
+			// the event carries the authority's session key, which is `0` for every validator
+			TestEvent::im_online(RawEvent::HeartbeatReceived(UintAuthorityId(0))),
+		);
 
 		// and when
 		let _ = heartbeat(1, 2, 2, 3.into()).unwrap();
@@ -185,6 +270,10 @@ fn should_mark_online_validator_when_heartbeat_is_received() {
 		assert!(ImOnline::is_online(0));
 		assert!(!ImOnline::is_online(1));
 		assert!(ImOnline::is_online(2));
+		assert_eq!(
+			System::events().last().unwrap().event,
+			TestEvent::im_online(RawEvent::HeartbeatReceived(UintAuthorityId(0))),
+		);
 	});
 }
 
@@ -272,6 +361,73 @@ fn should_generate_heartbeats() {
 	});
 }
 
+#[test]
+fn should_not_resend_heartbeat_called_twice_for_the_same_block() {
+	use sp_runtime::traits::OffchainWorker;
+
+	let mut ext = new_test_ext();
+	let (offchain, _state) = TestOffchainExt::new();
+	let (pool, state) = TestTransactionPoolExt::new();
+	ext.register_extension(OffchainExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		// given
+		System::set_block_number(1);
+		UintAuthorityId::set_all_keys(vec![0]);
+		// buffer a single validator, then enact it as the only authority of session 2
+		let validators = vec![UintAuthorityId(1)];
+		VALIDATORS.with(|l| *l.borrow_mut() = Some(validators));
+		Session::rotate_session();
+		Session::rotate_session();
+
+		// when the offchain worker is invoked twice back-to-back for the same block, the second
+		// run finds the heartbeat recorded by the first one and sends nothing
+		ImOnline::offchain_worker(1);
+		ImOnline::offchain_worker(1);
+
+		// then
+		assert_eq!(state.read().transactions.len(), 1);
+	});
+}
+
+#[test]
+fn should_not_resend_heartbeat_within_cooldown() {
+	use sp_runtime::traits::OffchainWorker;
+
+	let mut ext = new_test_ext();
+	let (offchain, _state) = TestOffchainExt::new();
+	let (pool, state) = TestTransactionPoolExt::new();
+	ext.register_extension(OffchainExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	ext.execute_with(|| {
+		// given
+		System::set_block_number(1);
+		UintAuthorityId::set_all_keys(vec![0]);
+		// buffer a single validator, then enact it as the only authority of session 2
+		let validators = vec![UintAuthorityId(1)];
+		VALIDATORS.with(|l| *l.borrow_mut() = Some(validators));
+		Session::rotate_session();
+		Session::rotate_session();
+
+		// when the offchain worker runs on several consecutive blocks, all still within the
+		// cooldown window and before the first heartbeat has actually been included on-chain
+		ImOnline::offchain_worker(1);
+		System::set_block_number(2);
+		ImOnline::offchain_worker(2);
+
+		// then only the first run's transaction lands in the pool
+		assert_eq!(state.read().transactions.len(), 1);
+
+		// and once the cooldown (2 blocks, see `mock::HeartbeatCooldown`) has elapsed a fresh
+		// heartbeat is sent again
+		System::set_block_number(3);
+		ImOnline::offchain_worker(3);
+		assert_eq!(state.read().transactions.len(), 2);
+	});
+}
+
 #[test]
 fn should_cleanup_received_heartbeats_on_session_end() {
 	new_test_ext().execute_with(|| {
@@ -352,6 +508,60 @@ fn should_mark_online_validator_when_block_is_authored() {
 	});
 }
 
+#[test]
+fn current_session_liveness_reports_online_and_offline_validators() {
+	use pallet_authorship::EventHandler;
+
+	new_test_ext().execute_with(|| {
+		advance_session();
+		// given
+		let validators = vec![
+			UintAuthorityId(1),
+			UintAuthorityId(2),
+			UintAuthorityId(3),
+			UintAuthorityId(4),
+			UintAuthorityId(5),
+			UintAuthorityId(6),
+		];
+		VALIDATORS.with(|l| *l.borrow_mut() = Some(validators.clone()));
+		assert_eq!(Session::validators(), Vec::<UintAuthorityId>::new());
+		// enact the change and buffer another one
+		advance_session();
+
+		assert_eq!(Session::current_index(), 2);
+		assert_eq!(Session::validators(), vec![
+			UintAuthorityId(1),
+			UintAuthorityId(2),
+			UintAuthorityId(3),
+		]);
+
+		// nobody has a heartbeat or an authored block yet
+		assert_eq!(
+			ImOnline::current_session_liveness(),
+			vec![
+				(UintAuthorityId(1), false),
+				(UintAuthorityId(2), false),
+				(UintAuthorityId(3), false),
+			],
+		);
+
+		// when validator 0 sends a heartbeat and validator 1 authors a block, but validator 2
+		// does neither
+		let _ = heartbeat(1, 2, 0, 1.into()).unwrap();
+		ImOnline::note_author(UintAuthorityId(2));
+
+		// then
+		assert_eq!(
+			ImOnline::current_session_liveness(),
+			vec![
+				(UintAuthorityId(1), true),
+				(UintAuthorityId(2), true),
+				(UintAuthorityId(3), false),
+			],
+		);
+	});
+}
+
 #[test]
 fn should_not_send_a_report_if_already_online() {
 	use pallet_authorship::EventHandler;